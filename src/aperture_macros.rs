@@ -0,0 +1,134 @@
+//! Aperture macros (`%AM%`): user-defined apertures built from parametric primitives.
+
+/// A user-defined aperture macro, as created by the `%AM<name>*...*%` extended code.
+///
+/// The macro body is an ordered list of [`MacroContent`](enum.MacroContent.html) primitives,
+/// each of which may reference the variables (`$1`, `$2`, ...) bound by the modifiers passed
+/// to the aperture that instantiates the macro (see `Aperture::Macro`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApertureMacro {
+    pub name: String,
+    pub content: Vec<MacroContent>,
+}
+
+impl ApertureMacro {
+    /// Create a new, empty aperture macro with the given name.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        ApertureMacro { name: name.into(), content: vec![] }
+    }
+
+    /// Append a primitive to the macro body.
+    pub fn add_content(mut self, content: MacroContent) -> Self {
+        self.content.push(content);
+        self
+    }
+}
+
+/// A single primitive statement inside an aperture macro body.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroContent {
+    /// A human readable comment (primitive code 0).
+    Comment(String),
+    /// A filled circle (primitive code 1).
+    Circle(CirclePrimitive),
+    /// A straight line defined by its centerline and width (primitive code 20).
+    VectorLine(VectorLinePrimitive),
+    /// A filled rectangle defined by its center, width and height (primitive code 21).
+    CenterLine(CenterLinePrimitive),
+    /// A filled outline described by a closed polygon (primitive code 4).
+    Outline(OutlinePrimitive),
+    /// A filled regular polygon (primitive code 5).
+    Polygon(PolygonPrimitive),
+    /// A thermal relief: a ring interrupted by gaps (primitive code 7).
+    Thermal(ThermalPrimitive),
+}
+
+/// Primitive code 1: a filled circle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CirclePrimitive {
+    pub exposure: MacroDecimal,
+    pub diameter: MacroDecimal,
+    pub center: (MacroDecimal, MacroDecimal),
+    pub rotation: MacroDecimal,
+}
+
+/// Primitive code 20: a straight line of a given width between two points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorLinePrimitive {
+    pub exposure: MacroDecimal,
+    pub width: MacroDecimal,
+    pub start: (MacroDecimal, MacroDecimal),
+    pub end: (MacroDecimal, MacroDecimal),
+    pub rotation: MacroDecimal,
+}
+
+/// Primitive code 21: a rectangle defined by its center, width and height.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CenterLinePrimitive {
+    pub exposure: MacroDecimal,
+    pub width: MacroDecimal,
+    pub height: MacroDecimal,
+    pub center: (MacroDecimal, MacroDecimal),
+    pub rotation: MacroDecimal,
+}
+
+/// Primitive code 4: an outline described by its vertices.
+///
+/// Per the Gerber spec, the first and last vertex must coincide so that the outline is closed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlinePrimitive {
+    pub exposure: MacroDecimal,
+    pub points: Vec<(MacroDecimal, MacroDecimal)>,
+    pub rotation: MacroDecimal,
+}
+
+/// Primitive code 5: a filled regular polygon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolygonPrimitive {
+    pub exposure: MacroDecimal,
+    pub vertices: MacroDecimal,
+    pub center: (MacroDecimal, MacroDecimal),
+    pub diameter: MacroDecimal,
+    pub rotation: MacroDecimal,
+}
+
+/// Primitive code 7: a thermal relief, i.e. a ring between the outer and inner diameters,
+/// interrupted by four gaps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalPrimitive {
+    pub center: (MacroDecimal, MacroDecimal),
+    pub outer_diameter: MacroDecimal,
+    pub inner_diameter: MacroDecimal,
+    pub gap: MacroDecimal,
+    pub rotation: MacroDecimal,
+}
+
+/// A numeric aperture macro modifier: a literal, a variable reference, or an expression.
+///
+/// Macro primitives are parameterized by the modifiers (`$1`, `$2`, ...) passed as `args`
+/// to `Aperture::Macro`, so any numeric field in a primitive may need to be an expression
+/// rather than a plain literal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroDecimal {
+    /// A literal value, e.g. `1.5`.
+    Value(f64),
+    /// A reference to a macro variable, e.g. `$1`.
+    Variable(u32),
+    /// An arithmetic expression combining two modifiers, e.g. `$1x1.5`.
+    Expression(Box<MacroDecimal>, MacroOperator, Box<MacroDecimal>),
+}
+
+impl From<f64> for MacroDecimal {
+    fn from(value: f64) -> Self {
+        MacroDecimal::Value(value)
+    }
+}
+
+/// The arithmetic operators allowed in an aperture macro modifier expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MacroOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
@@ -0,0 +1,16 @@
+//! File attributes (the `%TF` extended code). Aperture (`%TA`) and object (`%TO`) attributes
+//! are not modelled.
+
+/// A file attribute, attached to the Gerber file with the `%TF` extended code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileAttribute {
+    /// The `.Part` standard attribute, describing what the layer represents.
+    Part(Part),
+}
+
+/// The `.Part` file attribute value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part {
+    /// A part type not covered by the standard values, with a free-form description.
+    Other(String),
+}
@@ -0,0 +1,520 @@
+//! Code generation: turn the type tree back into Gerber source text.
+//!
+//! Serialization is writer-based (mirroring the design used by this crate before 0.4): every
+//! type writes itself directly into a `Write` implementor instead of allocating an intermediate
+//! `String`, which matters once a board has millions of operations. [`GerberCode`](trait.GerberCode.html)
+//! serializes a full, terminated statement (e.g. a whole `Command`); [`PartialGerberCode`](trait.PartialGerberCode.html)
+//! serializes a fragment with no trailing `*` or newline (e.g. just `Coordinates`), for types that
+//! only ever appear nested inside a full statement. [`ToCode`](trait.ToCode.html) is a thin
+//! `String`-returning adapter kept for backwards compatibility.
+
+use std::io::Write;
+
+use aperture_macros::*;
+use attributes::{FileAttribute, Part};
+use coordinates::{CoordinateOffset, Coordinates};
+use errors::GerberResult;
+use types::*;
+
+/// A type that can serialize itself as a complete, terminated Gerber statement.
+pub trait GerberCode<W: Write> {
+    /// Serialize `self` into `writer`, including its trailing `*` (and, for a list of
+    /// commands, the newlines separating them). Excellon statements are line-based and are
+    /// written without a `*`.
+    fn serialize(&self, writer: &mut W) -> GerberResult<()>;
+}
+
+/// A type that can serialize itself as a Gerber code fragment.
+///
+/// Unlike [`GerberCode`](trait.GerberCode.html), a `PartialGerberCode` implementor writes no
+/// trailing `*` or newline of its own; it only ever appears nested inside a full statement.
+pub trait PartialGerberCode<W: Write> {
+    /// Serialize `self` into `writer`, without any trailing terminator.
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()>;
+}
+
+/// A thin `String`-building adapter over [`GerberCode`](trait.GerberCode.html) /
+/// [`PartialGerberCode`](trait.PartialGerberCode.html), kept for callers that don't need to
+/// stream into a writer.
+pub trait ToCode {
+    /// Serialize `self` into a freshly allocated `String`.
+    fn to_code(&self) -> GerberResult<String>;
+}
+
+/// Implement `ToCode` for a full, statement-level type in terms of `GerberCode::serialize`.
+macro_rules! impl_to_code {
+    ($ty:ty) => {
+        impl ToCode for $ty {
+            fn to_code(&self) -> GerberResult<String> {
+                let mut buf = Vec::new();
+                self.serialize(&mut buf)?;
+                Ok(String::from_utf8(buf).expect("Gerber code is always valid UTF-8"))
+            }
+        }
+    };
+}
+
+/// Implement `ToCode` for a fragment type in terms of `PartialGerberCode::serialize_partial`.
+macro_rules! impl_to_code_partial {
+    ($ty:ty) => {
+        impl ToCode for $ty {
+            fn to_code(&self) -> GerberResult<String> {
+                let mut buf = Vec::new();
+                self.serialize_partial(&mut buf)?;
+                Ok(String::from_utf8(buf).expect("Gerber code is always valid UTF-8"))
+            }
+        }
+    };
+}
+
+impl<T, W> GerberCode<W> for Vec<T>
+where
+    T: GerberCode<W>,
+    W: Write,
+{
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        for (i, item) in self.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b"\n")?;
+            }
+            item.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: GerberCode<Vec<u8>>> ToCode for Vec<T> {
+    fn to_code(&self) -> GerberResult<String> {
+        let mut buf = Vec::new();
+        self.serialize(&mut buf)?;
+        Ok(String::from_utf8(buf).expect("Gerber code is always valid UTF-8"))
+    }
+}
+
+impl<W: Write> GerberCode<W> for Command {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            Command::FunctionCode(ref code) => code.serialize(writer),
+            Command::ExtendedCode(ref code) => code.serialize(writer),
+        }
+    }
+}
+impl_to_code!(Command);
+
+impl<W: Write> GerberCode<W> for FunctionCode {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            FunctionCode::GCode(ref code) => code.serialize(writer),
+            FunctionCode::MCode(ref code) => code.serialize(writer),
+            FunctionCode::DCode(ref code) => code.serialize(writer),
+        }
+    }
+}
+impl_to_code!(FunctionCode);
+
+impl<W: Write> GerberCode<W> for GCode {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            GCode::Comment(ref comment) => Ok(write!(writer, "G04 {} *", comment)?),
+            GCode::InterpolationMode(ref mode) => mode.serialize(writer),
+            GCode::RegionMode(enabled) => {
+                Ok(writer.write_all(if enabled { b"G36*" } else { b"G37*" })?)
+            },
+            GCode::QuadrantMode(ref mode) => mode.serialize(writer),
+        }
+    }
+}
+impl_to_code!(GCode);
+
+impl<W: Write> GerberCode<W> for InterpolationMode {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            InterpolationMode::Linear => b"G01*",
+            InterpolationMode::ClockwiseCircular => b"G02*",
+            InterpolationMode::CounterclockwiseCircular => b"G03*",
+        })?)
+    }
+}
+impl_to_code!(InterpolationMode);
+
+impl<W: Write> GerberCode<W> for QuadrantMode {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            QuadrantMode::Single => b"G74*",
+            QuadrantMode::Multi => b"G75*",
+        })?)
+    }
+}
+impl_to_code!(QuadrantMode);
+
+impl<W: Write> GerberCode<W> for MCode {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            MCode::EndOfFile => b"M02*",
+        })?)
+    }
+}
+impl_to_code!(MCode);
+
+impl<W: Write> GerberCode<W> for DCode {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            DCode::SelectAperture(code) => Ok(write!(writer, "D{}*", code)?),
+            DCode::Operation(ref operation) => operation.serialize(writer),
+        }
+    }
+}
+impl_to_code!(DCode);
+
+impl<W: Write> GerberCode<W> for Operation {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            Operation::Interpolate(ref coords, ref offset) => {
+                coords.serialize_partial(writer)?;
+                if let Some(ref offset) = *offset {
+                    offset.serialize_partial(writer)?;
+                }
+                Ok(write!(writer, "D01*")?)
+            },
+            Operation::Move(ref coords) => {
+                coords.serialize_partial(writer)?;
+                Ok(write!(writer, "D02*")?)
+            },
+            Operation::Flash(ref coords) => {
+                coords.serialize_partial(writer)?;
+                Ok(write!(writer, "D03*")?)
+            },
+        }
+    }
+}
+impl_to_code!(Operation);
+
+impl<W: Write> GerberCode<W> for ExtendedCode {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            ExtendedCode::CoordinateFormat(integer, decimal) => {
+                Ok(write!(writer, "%FSLAX{i}{d}Y{i}{d}*%", i = integer, d = decimal)?)
+            },
+            ExtendedCode::Unit(ref unit) => {
+                write!(writer, "%MO")?;
+                unit.serialize_partial(writer)?;
+                Ok(write!(writer, "*%")?)
+            },
+            ExtendedCode::ApertureDefinition(ref def) => {
+                write!(writer, "%AD")?;
+                def.serialize_partial(writer)?;
+                Ok(write!(writer, "*%")?)
+            },
+            ExtendedCode::LoadPolarity(ref polarity) => {
+                write!(writer, "%LP")?;
+                polarity.serialize_partial(writer)?;
+                Ok(write!(writer, "*%")?)
+            },
+            ExtendedCode::StepAndRepeat(ref sr) => sr.serialize(writer),
+            ExtendedCode::DeleteAttribute(ref name) => Ok(write!(writer, "%TD{}*%", name)?),
+            ExtendedCode::FileAttribute(ref attr) => {
+                write!(writer, "%TF")?;
+                attr.serialize_partial(writer)?;
+                Ok(write!(writer, "*%")?)
+            },
+            ExtendedCode::ApertureMacro(ref macro_) => {
+                write!(writer, "%AM")?;
+                macro_.serialize_partial(writer)?;
+                Ok(write!(writer, "%")?)
+            },
+        }
+    }
+}
+impl_to_code!(ExtendedCode);
+
+impl<W: Write> PartialGerberCode<W> for Unit {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            Unit::Millimeters => b"MM",
+            Unit::Inches => b"IN",
+        })?)
+    }
+}
+impl_to_code_partial!(Unit);
+
+impl<W: Write> PartialGerberCode<W> for Polarity {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            Polarity::Dark => b"D",
+            Polarity::Clear => b"C",
+        })?)
+    }
+}
+impl_to_code_partial!(Polarity);
+
+impl<W: Write> GerberCode<W> for StepAndRepeat {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            StepAndRepeat::Open { repeat_x, repeat_y, distance_x, distance_y } => {
+                Ok(write!(writer, "%SRX{}Y{}I{}J{}*%", repeat_x, repeat_y, distance_x, distance_y)?)
+            },
+            StepAndRepeat::Close => Ok(writer.write_all(b"%SR*%")?),
+        }
+    }
+}
+impl_to_code!(StepAndRepeat);
+
+impl<W: Write> PartialGerberCode<W> for Coordinates {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        if let Some(x) = self.x {
+            write!(writer, "X{}", x)?;
+        }
+        if let Some(y) = self.y {
+            write!(writer, "Y{}", y)?;
+        }
+        Ok(())
+    }
+}
+impl_to_code_partial!(Coordinates);
+
+impl<W: Write> PartialGerberCode<W> for CoordinateOffset {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        if let Some(x) = self.x {
+            write!(writer, "I{}", x)?;
+        }
+        if let Some(y) = self.y {
+            write!(writer, "J{}", y)?;
+        }
+        Ok(())
+    }
+}
+impl_to_code_partial!(CoordinateOffset);
+
+impl<W: Write> PartialGerberCode<W> for ApertureDefinition {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        write!(writer, "{}", self.code)?;
+        self.aperture.serialize_partial(writer)
+    }
+}
+impl_to_code_partial!(ApertureDefinition);
+
+impl<W: Write> PartialGerberCode<W> for Aperture {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            Aperture::Circle(ref circle) => {
+                write!(writer, "C,{}", circle.diameter)?;
+                if let Some(hole) = circle.hole_diameter {
+                    write!(writer, "X{}", hole)?;
+                }
+                Ok(())
+            },
+            Aperture::Rectangle(ref rect) => {
+                write!(writer, "R,{}X{}", rect.x, rect.y)?;
+                if let Some(hole) = rect.hole_diameter {
+                    write!(writer, "X{}", hole)?;
+                }
+                Ok(())
+            },
+            Aperture::Obround(ref rect) => {
+                write!(writer, "O,{}X{}", rect.x, rect.y)?;
+                if let Some(hole) = rect.hole_diameter {
+                    write!(writer, "X{}", hole)?;
+                }
+                Ok(())
+            },
+            Aperture::Polygon(ref polygon) => {
+                write!(writer, "P,{}X{}", polygon.diameter, polygon.vertices)?;
+                if let Some(rotation) = polygon.rotation {
+                    write!(writer, "X{}", rotation)?;
+                } else if polygon.hole_diameter.is_some() {
+                    write!(writer, "X0")?;
+                }
+                if let Some(hole) = polygon.hole_diameter {
+                    write!(writer, "X{}", hole)?;
+                }
+                Ok(())
+            },
+            Aperture::Macro { ref name, ref args } => {
+                write!(writer, "{}", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    write!(writer, "{}", if i == 0 { "," } else { "X" })?;
+                    arg.serialize_partial(writer)?;
+                }
+                Ok(())
+            },
+            Aperture::Other(ref other) => Ok(write!(writer, "{}", other)?),
+        }
+    }
+}
+impl_to_code_partial!(Aperture);
+
+impl<W: Write> PartialGerberCode<W> for FileAttribute {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            FileAttribute::Part(ref part) => {
+                write!(writer, ".Part,")?;
+                part.serialize_partial(writer)
+            },
+        }
+    }
+}
+impl_to_code_partial!(FileAttribute);
+
+impl<W: Write> PartialGerberCode<W> for Part {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            Part::Other(ref description) => Ok(write!(writer, "Other,{}", description)?),
+        }
+    }
+}
+impl_to_code_partial!(Part);
+
+impl<W: Write> PartialGerberCode<W> for MacroOperator {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            MacroOperator::Add => b"+",
+            MacroOperator::Subtract => b"-",
+            MacroOperator::Multiply => b"x",
+            MacroOperator::Divide => b"/",
+        })?)
+    }
+}
+impl_to_code_partial!(MacroOperator);
+
+impl<W: Write> PartialGerberCode<W> for MacroDecimal {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            MacroDecimal::Value(value) => Ok(write!(writer, "{}", value)?),
+            MacroDecimal::Variable(number) => Ok(write!(writer, "${}", number)?),
+            MacroDecimal::Expression(ref left, op, ref right) => {
+                left.serialize_partial(writer)?;
+                op.serialize_partial(writer)?;
+                right.serialize_partial(writer)
+            },
+        }
+    }
+}
+impl_to_code_partial!(MacroDecimal);
+
+/// Write an x/y modifier pair as the comma-separated fields used by macro primitives.
+fn write_point<W: Write>(point: &(MacroDecimal, MacroDecimal), writer: &mut W) -> GerberResult<()> {
+    point.0.serialize_partial(writer)?;
+    write!(writer, ",")?;
+    point.1.serialize_partial(writer)
+}
+
+impl<W: Write> PartialGerberCode<W> for MacroContent {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            MacroContent::Comment(ref comment) => Ok(write!(writer, "0 {}", comment)?),
+            MacroContent::Circle(ref primitive) => primitive.serialize_partial(writer),
+            MacroContent::VectorLine(ref primitive) => primitive.serialize_partial(writer),
+            MacroContent::CenterLine(ref primitive) => primitive.serialize_partial(writer),
+            MacroContent::Outline(ref primitive) => primitive.serialize_partial(writer),
+            MacroContent::Polygon(ref primitive) => primitive.serialize_partial(writer),
+            MacroContent::Thermal(ref primitive) => primitive.serialize_partial(writer),
+        }
+    }
+}
+impl_to_code_partial!(MacroContent);
+
+impl<W: Write> PartialGerberCode<W> for CirclePrimitive {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        write!(writer, "1,")?;
+        self.exposure.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.diameter.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        write_point(&self.center, writer)?;
+        write!(writer, ",")?;
+        self.rotation.serialize_partial(writer)
+    }
+}
+impl_to_code_partial!(CirclePrimitive);
+
+impl<W: Write> PartialGerberCode<W> for VectorLinePrimitive {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        write!(writer, "20,")?;
+        self.exposure.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.width.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        write_point(&self.start, writer)?;
+        write!(writer, ",")?;
+        write_point(&self.end, writer)?;
+        write!(writer, ",")?;
+        self.rotation.serialize_partial(writer)
+    }
+}
+impl_to_code_partial!(VectorLinePrimitive);
+
+impl<W: Write> PartialGerberCode<W> for CenterLinePrimitive {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        write!(writer, "21,")?;
+        self.exposure.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.width.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.height.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        write_point(&self.center, writer)?;
+        write!(writer, ",")?;
+        self.rotation.serialize_partial(writer)
+    }
+}
+impl_to_code_partial!(CenterLinePrimitive);
+
+impl<W: Write> PartialGerberCode<W> for OutlinePrimitive {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        write!(writer, "4,")?;
+        self.exposure.serialize_partial(writer)?;
+        write!(writer, ",{}", self.points.len())?;
+        for point in &self.points {
+            write!(writer, ",")?;
+            write_point(point, writer)?;
+        }
+        write!(writer, ",")?;
+        self.rotation.serialize_partial(writer)
+    }
+}
+impl_to_code_partial!(OutlinePrimitive);
+
+impl<W: Write> PartialGerberCode<W> for PolygonPrimitive {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        write!(writer, "5,")?;
+        self.exposure.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.vertices.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        write_point(&self.center, writer)?;
+        write!(writer, ",")?;
+        self.diameter.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.rotation.serialize_partial(writer)
+    }
+}
+impl_to_code_partial!(PolygonPrimitive);
+
+impl<W: Write> PartialGerberCode<W> for ThermalPrimitive {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        write!(writer, "7,")?;
+        write_point(&self.center, writer)?;
+        write!(writer, ",")?;
+        self.outer_diameter.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.inner_diameter.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.gap.serialize_partial(writer)?;
+        write!(writer, ",")?;
+        self.rotation.serialize_partial(writer)
+    }
+}
+impl_to_code_partial!(ThermalPrimitive);
+
+impl<W: Write> PartialGerberCode<W> for ApertureMacro {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        write!(writer, "{}*", self.name)?;
+        for primitive in &self.content {
+            writer.write_all(b"\n")?;
+            primitive.serialize_partial(writer)?;
+            write!(writer, "*")?;
+        }
+        Ok(())
+    }
+}
+impl_to_code_partial!(ApertureMacro);
@@ -0,0 +1,52 @@
+//! Coordinate related types.
+
+/// An X/Y coordinate pair.
+///
+/// Both axes are optional, since an operation does not need to repeat an
+/// axis that is unchanged from the previous operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+}
+
+impl Coordinates {
+    /// Create a new coordinate pair with both axes set.
+    pub fn new(x: i32, y: i32) -> Self {
+        Coordinates { x: Some(x), y: Some(y) }
+    }
+
+    /// Create a coordinate pair with only the X axis set.
+    pub fn at_x(x: i32) -> Self {
+        Coordinates { x: Some(x), y: None }
+    }
+
+    /// Create a coordinate pair with only the Y axis set.
+    pub fn at_y(y: i32) -> Self {
+        Coordinates { x: None, y: Some(y) }
+    }
+}
+
+/// An I/J offset pair, used by circular interpolation operations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateOffset {
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+}
+
+impl CoordinateOffset {
+    /// Create a new offset with both axes set.
+    pub fn new(x: i32, y: i32) -> Self {
+        CoordinateOffset { x: Some(x), y: Some(y) }
+    }
+
+    /// Create an offset with only the I axis set.
+    pub fn at_x(x: i32) -> Self {
+        CoordinateOffset { x: Some(x), y: None }
+    }
+
+    /// Create an offset with only the J axis set.
+    pub fn at_y(y: i32) -> Self {
+        CoordinateOffset { x: None, y: Some(y) }
+    }
+}
@@ -0,0 +1,41 @@
+//! Error types used throughout this crate.
+
+use std::fmt;
+use std::io;
+
+quick_error! {
+    /// The error type used by this crate.
+    #[derive(Debug)]
+    pub enum GerberError {
+        /// Wraps an I/O error that occurred while reading or writing Gerber code.
+        IoError(err: io::Error) {
+            display("I/O error: {}", err)
+            from()
+        }
+        /// Wraps a formatting error that occurred while building Gerber code.
+        FmtError(err: fmt::Error) {
+            display("Formatting error: {}", err)
+            from()
+        }
+        /// A generic error with a custom message.
+        GenericError(msg: String) {
+            display("Error: {}", msg)
+        }
+        /// A Gerber word that the parser does not recognize.
+        UnknownCommand(word: String) {
+            display("Unknown Gerber command: {}", word)
+        }
+        /// An aperture definition (`%AD%`) or aperture macro primitive (`%AM%`) that does not
+        /// match any known syntax.
+        MalformedApertureDefinition(msg: String) {
+            display("Malformed aperture definition: {}", msg)
+        }
+        /// Gerber source that ended in the middle of a statement or `%...%` block.
+        UnexpectedEof {
+            display("Unexpected end of input while parsing Gerber code")
+        }
+    }
+}
+
+/// A `Result` alias where the error case is always a [`GerberError`](enum.GerberError.html).
+pub type GerberResult<T> = Result<T, GerberError>;
@@ -0,0 +1,174 @@
+//! Excellon (NC drill) commands: a sibling format to Gerber that describes drill hits and
+//! routed slots rather than copper.
+//!
+//! An Excellon file is line-based rather than `*`-terminated like Gerber: a header section
+//! (opened by [`HeaderCommand::BeginHeader`](enum.HeaderCommand.html) / `M48` and closed by
+//! [`HeaderCommand::EndHeader`](enum.HeaderCommand.html) / `%`) sets the unit, zero suppression
+//! and coordinate format, and declares the tools in use; the body then selects a tool and either
+//! drills a hole at a [`Coordinates`](struct.Coordinates.html) or routs a slot with a plunge/cut/
+//! retract sequence, ending in `M30`.
+
+use std::io::Write;
+
+use codegen::{GerberCode, PartialGerberCode, ToCode};
+use coordinates::Coordinates;
+use errors::GerberResult;
+
+/// A full Excellon command: either a header directive or a body statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExcellonCommand {
+    Header(HeaderCommand),
+    Body(BodyCommand),
+}
+
+/// A header section command, found between `M48` and the `%` that ends it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderCommand {
+    /// Begins the header (`M48`).
+    BeginHeader,
+    /// Sets the unit and zero suppression used by coordinates and tool diameters.
+    Units(ExcellonUnit, ZeroSuppression),
+    /// Sets the coordinate format, as `(integer_digits, decimal_digits)`.
+    CoordinateFormat(u8, u8),
+    /// Defines a tool: maps a tool number to a drill diameter (e.g. `T01C0.800`).
+    ToolDefinition(ToolDefinition),
+    /// Ends the header (`%`).
+    EndHeader,
+}
+
+/// The unit used to interpret coordinates and tool diameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExcellonUnit {
+    Metric,
+    Inch,
+}
+
+/// Which zeros may be omitted when a coordinate is written with fewer than its full digit
+/// width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZeroSuppression {
+    /// Leading zeros are omitted (`LZ`).
+    Leading,
+    /// Trailing zeros are omitted (`TZ`).
+    Trailing,
+}
+
+/// A tool definition: a tool number paired with the diameter it drills (e.g. `T01C0.800`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToolDefinition {
+    pub code: i32,
+    pub diameter: f64,
+}
+
+/// A body section command, the drill hits and routed slots that make up the bulk of the file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyCommand {
+    /// Selects the current tool by its number (`T01`).
+    ToolSelect(i32),
+    /// Drills a hole at the given coordinates with the current tool.
+    Drill(Coordinates),
+    /// Sets the routing mode used to reach the next coordinates: rapid positioning (`G00`) or
+    /// a linear cut (`G01`).
+    RoutMode(RoutMode),
+    /// Plunges the current tool into the board, beginning a routed slot (`M15`).
+    Plunge,
+    /// Retracts the current tool, ending a routed slot (`M16`).
+    Retract,
+    /// Marks the end of the Excellon file (`M30`).
+    EndOfFile,
+}
+
+/// The routing mode used while cutting a slot.
+///
+/// A routed slot is a `G00` rapid move to the start point, an `M15` plunge, a `G01` linear cut
+/// to the end point, and an `M16` retract.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoutMode {
+    /// Rapid positioning: move without cutting (`G00`).
+    Rapid,
+    /// Linear routing: cut a straight slot to the given coordinates (`G01`).
+    Linear,
+}
+
+impl<W: Write> GerberCode<W> for ExcellonCommand {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            ExcellonCommand::Header(ref cmd) => cmd.serialize(writer),
+            ExcellonCommand::Body(ref cmd) => cmd.serialize(writer),
+        }
+    }
+}
+
+impl_to_code!(ExcellonCommand);
+
+impl<W: Write> GerberCode<W> for HeaderCommand {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            HeaderCommand::BeginHeader => Ok(writer.write_all(b"M48")?),
+            HeaderCommand::Units(unit, zero_suppression) => {
+                unit.serialize_partial(writer)?;
+                write!(writer, ",")?;
+                zero_suppression.serialize_partial(writer)
+            },
+            HeaderCommand::CoordinateFormat(integer, decimal) => {
+                Ok(write!(writer, ";FORMAT={{{}:{}}}", integer, decimal)?)
+            },
+            HeaderCommand::ToolDefinition(ref tool) => tool.serialize(writer),
+            HeaderCommand::EndHeader => Ok(writer.write_all(b"%")?),
+        }
+    }
+}
+
+impl_to_code!(HeaderCommand);
+
+impl<W: Write> PartialGerberCode<W> for ExcellonUnit {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            ExcellonUnit::Metric => b"METRIC",
+            ExcellonUnit::Inch => b"INCH",
+        })?)
+    }
+}
+
+impl<W: Write> PartialGerberCode<W> for ZeroSuppression {
+    fn serialize_partial(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            ZeroSuppression::Leading => b"LZ",
+            ZeroSuppression::Trailing => b"TZ",
+        })?)
+    }
+}
+
+impl<W: Write> GerberCode<W> for ToolDefinition {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(write!(writer, "T{:02}C{}", self.code, self.diameter)?)
+    }
+}
+
+impl_to_code!(ToolDefinition);
+
+impl<W: Write> GerberCode<W> for BodyCommand {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        match *self {
+            BodyCommand::ToolSelect(code) => Ok(write!(writer, "T{:02}", code)?),
+            BodyCommand::Drill(ref coords) => coords.serialize_partial(writer),
+            BodyCommand::RoutMode(ref mode) => mode.serialize(writer),
+            BodyCommand::Plunge => Ok(writer.write_all(b"M15")?),
+            BodyCommand::Retract => Ok(writer.write_all(b"M16")?),
+            BodyCommand::EndOfFile => Ok(writer.write_all(b"M30")?),
+        }
+    }
+}
+
+impl_to_code!(BodyCommand);
+
+impl<W: Write> GerberCode<W> for RoutMode {
+    fn serialize(&self, writer: &mut W) -> GerberResult<()> {
+        Ok(writer.write_all(match *self {
+            RoutMode::Rapid => b"G00",
+            RoutMode::Linear => b"G01",
+        })?)
+    }
+}
+
+impl_to_code!(RoutMode);
@@ -0,0 +1,188 @@
+//! Optional interop with the `geo` ecosystem (the `geo-types` crate), enabled by the `geo`
+//! cargo feature.
+//!
+//! Region fills (`G36`/`G37` contours) and flashed apertures are geometrically plain polygons;
+//! this module bridges them to [`geo_types::Polygon`](../geo_types/struct.Polygon.html) so that
+//! boolean and offset operations from the `geo`/clipper ecosystem can run directly against
+//! copper geometry extracted from a Gerber command tree, and so their result can be emitted
+//! back out as region commands via `Region::from(&polygon)`.
+
+use std::f64::consts::TAU;
+
+use coordinates::Coordinates;
+use geo_types::{Coord, LineString, Polygon as GeoPolygon};
+use types::{
+    Aperture, Circle, Command, DCode, FunctionCode, GCode, InterpolationMode, Operation,
+    Polygon as AperturePolygon, Rectangular,
+};
+
+/// Number of segments used to approximate a circular aperture as a polygon.
+const CIRCLE_SEGMENTS: u32 = 64;
+
+/// Turn the endpoints of a region's move/draw operations into a closed `LineString`.
+///
+/// Only the endpoint of each operation is used, so a circular interpolation segment is
+/// approximated by a straight line to its endpoint rather than subdivided into an arc. An axis
+/// that an operation omits keeps its value from the previous operation (0 before the first).
+pub fn region_outline(operations: &[Operation]) -> LineString<f64> {
+    let mut last = (0, 0);
+    let mut coords: Vec<Coord<f64>> = operations
+        .iter()
+        .map(|operation| {
+            let coordinates = match *operation {
+                Operation::Interpolate(coordinates, _) => coordinates,
+                Operation::Move(coordinates) => coordinates,
+                Operation::Flash(coordinates) => coordinates,
+            };
+            last = (coordinates.x.unwrap_or(last.0), coordinates.y.unwrap_or(last.1));
+            Coord { x: last.0 as f64, y: last.1 as f64 }
+        })
+        .collect();
+    if !coords.is_empty() && coords.first() != coords.last() {
+        coords.push(coords[0]);
+    }
+    LineString(coords)
+}
+
+/// Turn a region's move/draw operations into a filled `Polygon` with no interior rings.
+pub fn region_to_polygon(operations: &[Operation]) -> GeoPolygon<f64> {
+    GeoPolygon::new(region_outline(operations), vec![])
+}
+
+/// A region (`G36`…`G37`) command sequence, as emitted from a `geo_types::Polygon` by the
+/// `From` impl below.
+///
+/// This is a thin wrapper around `Vec<Command>`: `geo_types::Polygon`/`From` are both foreign to
+/// this crate, so a direct `impl From<GeoPolygon<f64>> for Vec<Command>` would violate Rust's
+/// orphan rules, and `Region` gives the conversion a local type to land on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region(pub Vec<Command>);
+
+impl From<Region> for Vec<Command> {
+    fn from(region: Region) -> Self {
+        region.0
+    }
+}
+
+/// Emit the `G36`…`G37` region command sequence that draws a polygon's exterior ring, followed
+/// by each interior ring (as the cutouts a region forms from overlapping contours), as straight
+/// `D01` interpolations.
+///
+/// A `geo_types::Polygon` only ever carries straight edges, so every segment is emitted as a
+/// linear interpolation; there is no curvature to reconstruct into an arc (`QuadrantMode` +
+/// `CoordinateOffset`), so none is emitted.
+impl From<&GeoPolygon<f64>> for Region {
+    fn from(polygon: &GeoPolygon<f64>) -> Self {
+        let mut commands = vec![
+            gcode(GCode::RegionMode(true)),
+            gcode(GCode::InterpolationMode(InterpolationMode::Linear)),
+        ];
+        push_ring(polygon.exterior(), &mut commands);
+        for interior in polygon.interiors() {
+            push_ring(interior, &mut commands);
+        }
+        commands.push(gcode(GCode::RegionMode(false)));
+        Region(commands)
+    }
+}
+
+fn gcode(code: GCode) -> Command {
+    Command::FunctionCode(FunctionCode::GCode(code))
+}
+
+fn push_ring(ring: &LineString<f64>, commands: &mut Vec<Command>) {
+    for (i, coord) in ring.coords().enumerate() {
+        let coordinates = Coordinates::new(coord.x.round() as i32, coord.y.round() as i32);
+        let operation = if i == 0 { Operation::Move(coordinates) } else { Operation::Interpolate(coordinates, None) };
+        commands.push(Command::FunctionCode(FunctionCode::DCode(DCode::Operation(operation))));
+    }
+}
+
+/// Build the filled outline of a flashed aperture, centered at `center`, as a `Polygon`.
+///
+/// Only `Aperture::Circle`, `Aperture::Rectangle` and `Aperture::Polygon` are supported; other
+/// aperture kinds (obrounds, macros, pass-through templates) have no fixed polygonal shape to
+/// derive, and return `None`.
+pub fn aperture_outline(aperture: &Aperture, center: Coordinates) -> Option<GeoPolygon<f64>> {
+    let cx = center.x.unwrap_or(0) as f64;
+    let cy = center.y.unwrap_or(0) as f64;
+    match *aperture {
+        Aperture::Circle(Circle { diameter, .. }) => {
+            Some(regular_polygon(cx, cy, diameter / 2.0, CIRCLE_SEGMENTS, 0.0))
+        },
+        Aperture::Rectangle(Rectangular { x, y, .. }) => {
+            let (hx, hy) = (x / 2.0, y / 2.0);
+            Some(GeoPolygon::new(
+                LineString(vec![
+                    Coord { x: cx - hx, y: cy - hy },
+                    Coord { x: cx + hx, y: cy - hy },
+                    Coord { x: cx + hx, y: cy + hy },
+                    Coord { x: cx - hx, y: cy + hy },
+                    Coord { x: cx - hx, y: cy - hy },
+                ]),
+                vec![],
+            ))
+        },
+        Aperture::Polygon(AperturePolygon { diameter, vertices, rotation, .. }) => {
+            Some(regular_polygon(cx, cy, diameter / 2.0, vertices as u32, rotation.unwrap_or(0.0)))
+        },
+        _ => None,
+    }
+}
+
+/// Build a regular polygon outline: `sides` vertices at `radius` from `(cx, cy)`, with the
+/// first vertex rotated `rotation` degrees from the X axis.
+fn regular_polygon(cx: f64, cy: f64, radius: f64, sides: u32, rotation: f64) -> GeoPolygon<f64> {
+    let offset = rotation.to_radians();
+    let mut coords: Vec<Coord<f64>> = (0..sides)
+        .map(|i| {
+            let angle = offset + f64::from(i) * TAU / f64::from(sides);
+            Coord { x: cx + radius * angle.cos(), y: cy + radius * angle.sin() }
+        })
+        .collect();
+    coords.push(coords[0]);
+    GeoPolygon::new(LineString(coords), vec![])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use codegen::ToCode;
+
+    #[test]
+    fn test_region_outline_round_trip() {
+        let operations = vec![
+            Operation::Move(Coordinates::new(0, 0)),
+            Operation::Interpolate(Coordinates::new(10, 0), None),
+            Operation::Interpolate(Coordinates::new(10, 10), None),
+            Operation::Interpolate(Coordinates::new(0, 10), None),
+        ];
+        let polygon = region_to_polygon(&operations);
+        let commands: Vec<Command> = Region::from(&polygon).into();
+        assert_eq!(
+            commands.to_code().unwrap(),
+            "G36*\nG01*\nX0Y0D02*\nX10Y0D01*\nX10Y10D01*\nX0Y10D01*\nX0Y0D01*\nG37*".to_string()
+        );
+    }
+
+    #[test]
+    fn test_aperture_outline_rectangle() {
+        let aperture = Aperture::Rectangle(Rectangular { x: 2.0, y: 4.0, hole_diameter: None });
+        let polygon = aperture_outline(&aperture, Coordinates::new(10, 10)).unwrap();
+        let points: Vec<(f64, f64)> = polygon.exterior().coords().map(|c| (c.x, c.y)).collect();
+        assert_eq!(points, vec![(9.0, 8.0), (11.0, 8.0), (11.0, 12.0), (9.0, 12.0), (9.0, 8.0)]);
+    }
+
+    #[test]
+    fn test_aperture_outline_circle_segment_count() {
+        let aperture = Aperture::Circle(Circle { diameter: 2.0, hole_diameter: None });
+        let polygon = aperture_outline(&aperture, Coordinates::new(0, 0)).unwrap();
+        assert_eq!(polygon.exterior().coords().count(), CIRCLE_SEGMENTS as usize + 1);
+    }
+
+    #[test]
+    fn test_aperture_outline_unsupported_shape() {
+        let aperture = Aperture::Other("FOO".to_string());
+        assert!(aperture_outline(&aperture, Coordinates::new(0, 0)).is_none());
+    }
+}
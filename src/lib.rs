@@ -14,21 +14,31 @@ extern crate chrono;
 extern crate uuid;
 extern crate conv;
 #[macro_use] extern crate quick_error;
+#[cfg(feature = "geo")] extern crate geo_types;
 
+mod aperture_macros;
 mod types;
 mod attributes;
-mod codegen;
+#[macro_use] mod codegen;
 mod coordinates;
 mod errors;
+mod excellon;
+#[cfg(feature = "geo")] mod geo;
+mod parser;
 
+pub use aperture_macros::*;
 pub use types::*;
 pub use attributes::*;
 pub use codegen::*;
 pub use coordinates::*;
 pub use errors::*;
+pub use excellon::*;
+#[cfg(feature = "geo")] pub use geo::*;
+pub use parser::*;
 
 
 #[cfg(test)]
+#[allow(clippy::vec_init_then_push)]
 mod test {
     use super::*;
 
@@ -264,4 +274,229 @@ mod test {
         assert_eq!(a.to_code().unwrap(), "%TF.Part,Other,foo*%".to_string());
     }
 
+    #[test]
+    fn test_parse_gerber_round_trip() {
+        //! Parsing a simple Gerber file and re-serializing it should reproduce it exactly.
+        let source = "G04 testcomment *\n%FSLAX25Y25*%\n%MOMM*%\n%AD10C,0.5*%\nD10*\nX100Y200D02*\nX150Y250D01*\nM02*";
+        let commands = parse_gerber(source.as_bytes()).unwrap();
+        assert_eq!(commands.to_code().unwrap(), source.to_string());
+    }
+
+    #[test]
+    fn test_parse_bare_flash_round_trip() {
+        //! A coordinate-less `D03*` is a flash at the current point, not an aperture selection.
+        let commands = parse_gerber("D03*".as_bytes()).unwrap();
+        assert_eq!(
+            commands,
+            vec![Command::FunctionCode(FunctionCode::DCode(DCode::Operation(
+                Operation::Flash(Coordinates { x: None, y: None })
+            )))]
+        );
+        assert_eq!(commands.to_code().unwrap(), "D03*".to_string());
+    }
+
+    #[test]
+    fn test_parse_aperture_definition() {
+        let ctx = ParseContext;
+        let ad = ApertureDefinition::parse("10C,4X2", &ctx).unwrap();
+        assert_eq!(ad, ApertureDefinition {
+            code: 10,
+            aperture: Aperture::Circle(Circle { diameter: 4.0, hole_diameter: Some(2.0) }),
+        });
+    }
+
+    #[test]
+    fn test_parse_aperture_macro() {
+        let ctx = ParseContext;
+        let am = ApertureMacro::parse("CIRC*\n0 a circle primitive*\n1,1,$1,0,0,0*", &ctx).unwrap();
+        assert_eq!(am, ApertureMacro::new("CIRC")
+            .add_content(MacroContent::Comment("a circle primitive".to_string()))
+            .add_content(MacroContent::Circle(CirclePrimitive {
+                exposure: MacroDecimal::Value(1.0),
+                diameter: MacroDecimal::Variable(1),
+                center: (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+                rotation: MacroDecimal::Value(0.0),
+            })));
+    }
+
+    #[test]
+    fn test_parse_aperture_macro_outline() {
+        let ctx = ParseContext;
+        let am = ApertureMacro::parse("OUT*\n4,1,3,0,0,1,0,1,1,0*", &ctx).unwrap();
+        assert_eq!(am, ApertureMacro::new("OUT")
+            .add_content(MacroContent::Outline(OutlinePrimitive {
+                exposure: MacroDecimal::Value(1.0),
+                points: vec![
+                    (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+                    (MacroDecimal::Value(1.0), MacroDecimal::Value(0.0)),
+                    (MacroDecimal::Value(1.0), MacroDecimal::Value(1.0)),
+                ],
+                rotation: MacroDecimal::Value(0.0),
+            })));
+    }
+
+    #[test]
+    fn test_parse_file_attribute_part_round_trip() {
+        let ctx = ParseContext;
+        let attr = FileAttribute::parse(".Part,Other,foo", &ctx).unwrap();
+        assert_eq!(attr, FileAttribute::Part(Part::Other("foo".to_string())));
+        assert_eq!(
+            ExtendedCode::FileAttribute(attr).to_code().unwrap(),
+            "%TF.Part,Other,foo*%".to_string()
+        );
+    }
+
+    #[test]
+    fn test_unknown_command_error() {
+        let result = parse_gerber("G99*".as_bytes());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_serialize_into_writer() {
+        //! `GerberCode::serialize` should write directly into a `Write` implementor.
+        let mut commands = Vec::new();
+        commands.push(Command::FunctionCode(FunctionCode::GCode(GCode::Comment("hi".to_string()))));
+        commands.push(Command::FunctionCode(FunctionCode::MCode(MCode::EndOfFile)));
+        let mut buf: Vec<u8> = Vec::new();
+        commands.serialize(&mut buf).unwrap();
+        assert_eq!(buf, b"G04 hi *\nM02*".to_vec());
+    }
+
+    #[test]
+    fn test_serialize_partial() {
+        //! `PartialGerberCode::serialize_partial` should write a fragment with no terminator.
+        let mut buf: Vec<u8> = Vec::new();
+        Coordinates::new(1, 2).serialize_partial(&mut buf).unwrap();
+        assert_eq!(buf, b"X1Y2".to_vec());
+    }
+
+    #[test]
+    fn test_macro_decimal_to_code() {
+        let literal = MacroDecimal::Value(1.5);
+        let variable = MacroDecimal::Variable(1);
+        let expression = MacroDecimal::Expression(
+            Box::new(MacroDecimal::Variable(1)),
+            MacroOperator::Multiply,
+            Box::new(MacroDecimal::Value(1.5)),
+        );
+        assert_eq!(literal.to_code().unwrap(), "1.5".to_string());
+        assert_eq!(variable.to_code().unwrap(), "$1".to_string());
+        assert_eq!(expression.to_code().unwrap(), "$1x1.5".to_string());
+    }
+
+    #[test]
+    fn test_macro_decimal_expression_parse_is_not_tree_stable() {
+        //! `MacroDecimal::parse` splits on the first operator, so a multi-operator expression
+        //! re-parses right-associatively regardless of how the original tree was nested (see
+        //! the doc comment on `impl Parse for MacroDecimal`). The round-tripped *source text*
+        //! stays stable even though the tree shape does not.
+        let ctx = ParseContext;
+        let left_nested = MacroDecimal::Expression(
+            Box::new(MacroDecimal::Expression(
+                Box::new(MacroDecimal::Value(1.0)),
+                MacroOperator::Add,
+                Box::new(MacroDecimal::Value(2.0)),
+            )),
+            MacroOperator::Subtract,
+            Box::new(MacroDecimal::Value(3.0)),
+        );
+        let source = left_nested.to_code().unwrap();
+        assert_eq!(source, "1+2-3".to_string());
+        let reparsed = MacroDecimal::parse(&source, &ctx).unwrap();
+        assert_ne!(reparsed, left_nested);
+        assert_eq!(reparsed.to_code().unwrap(), source);
+    }
+
+    #[test]
+    fn test_macro_circle_primitive_to_code() {
+        let circle = MacroContent::Circle(CirclePrimitive {
+            exposure: MacroDecimal::Value(1.0),
+            diameter: MacroDecimal::Variable(1),
+            center: (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+            rotation: MacroDecimal::Value(0.0),
+        });
+        assert_eq!(circle.to_code().unwrap(), "1,1,$1,0,0,0".to_string());
+    }
+
+    #[test]
+    fn test_macro_outline_primitive_to_code() {
+        let outline = MacroContent::Outline(OutlinePrimitive {
+            exposure: MacroDecimal::Value(1.0),
+            points: vec![
+                (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+                (MacroDecimal::Value(1.0), MacroDecimal::Value(0.0)),
+                (MacroDecimal::Value(1.0), MacroDecimal::Value(1.0)),
+            ],
+            rotation: MacroDecimal::Value(0.0),
+        });
+        assert_eq!(outline.to_code().unwrap(), "4,1,3,0,0,1,0,1,1,0".to_string());
+    }
+
+    #[test]
+    fn test_aperture_macro_to_code() {
+        let am = ApertureMacro::new("CIRC")
+            .add_content(MacroContent::Comment("a circle primitive".to_string()))
+            .add_content(MacroContent::Circle(CirclePrimitive {
+                exposure: MacroDecimal::Value(1.0),
+                diameter: MacroDecimal::Variable(1),
+                center: (MacroDecimal::Value(0.0), MacroDecimal::Value(0.0)),
+                rotation: MacroDecimal::Value(0.0),
+            }));
+        let expected = "%AMCIRC*\n0 a circle primitive*\n1,1,$1,0,0,0*%";
+        assert_eq!(
+            ExtendedCode::ApertureMacro(am).to_code().unwrap(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn test_aperture_macro_instantiation_to_code() {
+        let ad = ApertureDefinition {
+            code: 10,
+            aperture: Aperture::Macro { name: "CIRC".to_string(), args: vec![MacroDecimal::Value(1.5)] },
+        };
+        assert_eq!(ad.to_code().unwrap(), "10CIRC,1.5".to_string());
+    }
+
+    #[test]
+    fn test_excellon_tool_definition_to_code() {
+        let tool = ToolDefinition { code: 1, diameter: 0.8 };
+        assert_eq!(tool.to_code().unwrap(), "T01C0.8".to_string());
+    }
+
+    #[test]
+    fn test_excellon_header_to_code() {
+        let mut commands = Vec::new();
+        commands.push(ExcellonCommand::Header(HeaderCommand::BeginHeader));
+        commands.push(ExcellonCommand::Header(HeaderCommand::Units(ExcellonUnit::Metric, ZeroSuppression::Trailing)));
+        commands.push(ExcellonCommand::Header(HeaderCommand::CoordinateFormat(2, 4)));
+        commands.push(ExcellonCommand::Header(HeaderCommand::ToolDefinition(ToolDefinition { code: 1, diameter: 0.8 })));
+        commands.push(ExcellonCommand::Header(HeaderCommand::EndHeader));
+        let expected = "M48\nMETRIC,TZ\n;FORMAT={2:4}\nT01C0.8\n%";
+        assert_eq!(commands.to_code().unwrap(), expected.to_string());
+    }
+
+    #[test]
+    fn test_excellon_drill_hit_to_code() {
+        let hit = BodyCommand::Drill(Coordinates::new(1000, 2500));
+        assert_eq!(hit.to_code().unwrap(), "X1000Y2500".to_string());
+    }
+
+    #[test]
+    fn test_excellon_routed_slot_to_code() {
+        //! A routed slot is a rapid move to the start, a plunge, a linear cut, and a retract.
+        let mut commands = Vec::new();
+        commands.push(ExcellonCommand::Body(BodyCommand::ToolSelect(2)));
+        commands.push(ExcellonCommand::Body(BodyCommand::RoutMode(RoutMode::Rapid)));
+        commands.push(ExcellonCommand::Body(BodyCommand::Drill(Coordinates::new(0, 0))));
+        commands.push(ExcellonCommand::Body(BodyCommand::Plunge));
+        commands.push(ExcellonCommand::Body(BodyCommand::RoutMode(RoutMode::Linear)));
+        commands.push(ExcellonCommand::Body(BodyCommand::Drill(Coordinates::new(1000, 0))));
+        commands.push(ExcellonCommand::Body(BodyCommand::Retract));
+        commands.push(ExcellonCommand::Body(BodyCommand::EndOfFile));
+        let expected = "T02\nG00\nX0Y0\nM15\nG01\nX1000Y0\nM16\nM30";
+        assert_eq!(commands.to_code().unwrap(), expected.to_string());
+    }
+
 }
@@ -0,0 +1,608 @@
+//! A parser that reads RS-274X (Gerber) source back into the type tree.
+//!
+//! Parsing is built around the [`Parse`](trait.Parse.html) trait: each type knows how to parse
+//! itself from the token text that [`GerberCode::serialize`](trait.GerberCode.html) would have
+//! produced for it, given a [`ParseContext`](struct.ParseContext.html); the context currently
+//! carries no state, and `parse_gerber` does not track the active `%FS%` coordinate format
+//! (coordinates are kept as the raw integers written in the file).
+//! [`parse_gerber`](fn.parse_gerber.html) drives the whole
+//! process: it splits the input into `*`-terminated function codes and `%...%` extended codes,
+//! and dispatches each to `Parse`.
+
+use std::io::Read;
+
+use aperture_macros::*;
+use attributes::{FileAttribute, Part};
+use coordinates::{CoordinateOffset, Coordinates};
+use errors::{GerberError, GerberResult};
+use types::*;
+
+/// A type that can parse itself from the Gerber token text that would serialize back to it.
+pub trait Parse: Sized {
+    /// State threaded through parsing that earlier tokens may affect (see
+    /// [`ParseContext`](struct.ParseContext.html)).
+    type Context;
+
+    /// Parse `input` (a single token, with any surrounding `*`/`%` delimiters already removed).
+    fn parse(input: &str, ctx: &Self::Context) -> GerberResult<Self>;
+}
+
+/// State threaded through [`Parse::parse`](trait.Parse.html) calls while reading a Gerber file.
+///
+/// Nothing reads this yet: the only zero-suppression mode this crate emits or reads, `%FSLA%`
+/// (leading zero omission), never changes the value a digit run parses to, so coordinate
+/// parsing does not need to consult the active `%FS%` format. It's kept as the extension point
+/// a trailing-zero-suppression (`%FSTA%`) mode would need.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ParseContext;
+
+/// Parse the leading (optionally signed) run of decimal digits off `input`, returning the
+/// digit run and the remainder.
+fn take_number(input: &str) -> (&str, &str) {
+    let bytes = input.as_bytes();
+    let mut end = 0;
+    if end < bytes.len() && (bytes[end] == b'-' || bytes[end] == b'+') {
+        end += 1;
+    }
+    while end < bytes.len() && bytes[end].is_ascii_digit() {
+        end += 1;
+    }
+    input.split_at(end)
+}
+
+/// Like [`take_number`](fn.take_number.html), but also accepts a single decimal point.
+fn take_decimal(input: &str) -> (&str, &str) {
+    let bytes = input.as_bytes();
+    let mut end = 0;
+    if end < bytes.len() && (bytes[end] == b'-' || bytes[end] == b'+') {
+        end += 1;
+    }
+    while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.') {
+        end += 1;
+    }
+    input.split_at(end)
+}
+
+/// The raw X/Y/I/J modifiers found at the start of an operation or coordinate fragment.
+#[derive(Debug, Default)]
+struct CoordTokens {
+    x: Option<i32>,
+    y: Option<i32>,
+    i: Option<i32>,
+    j: Option<i32>,
+}
+
+/// Consume the leading run of `X`/`Y`/`I`/`J` modifiers off `input`, returning the parsed
+/// values and whatever is left (e.g. the `D01`/`D02`/`D03` suffix of an operation).
+fn take_coord_tokens(input: &str) -> GerberResult<(CoordTokens, &str)> {
+    let mut tokens = CoordTokens::default();
+    let mut rest = input;
+    while let Some(marker @ ('X' | 'Y' | 'I' | 'J')) = rest.chars().next() {
+        let (num, tail) = take_number(&rest[1..]);
+        let value: i32 = num
+            .parse()
+            .map_err(|_| GerberError::GenericError(format!("invalid numeric value in {:?}", input)))?;
+        match marker {
+            'X' => tokens.x = Some(value),
+            'Y' => tokens.y = Some(value),
+            'I' => tokens.i = Some(value),
+            'J' => tokens.j = Some(value),
+            _ => unreachable!(),
+        }
+        rest = tail;
+    }
+    Ok((tokens, rest))
+}
+
+impl Parse for Coordinates {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        let (tokens, rest) = take_coord_tokens(input)?;
+        if !rest.is_empty() {
+            return Err(GerberError::UnknownCommand(input.to_string()));
+        }
+        Ok(Coordinates { x: tokens.x, y: tokens.y })
+    }
+}
+
+impl Parse for CoordinateOffset {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        let (tokens, rest) = take_coord_tokens(input)?;
+        if !rest.is_empty() {
+            return Err(GerberError::UnknownCommand(input.to_string()));
+        }
+        Ok(CoordinateOffset { x: tokens.i, y: tokens.j })
+    }
+}
+
+impl Parse for Operation {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        let (tokens, rest) = take_coord_tokens(input)?;
+        let coords = Coordinates { x: tokens.x, y: tokens.y };
+        let offset = if tokens.i.is_some() || tokens.j.is_some() {
+            Some(CoordinateOffset { x: tokens.i, y: tokens.j })
+        } else {
+            None
+        };
+        match rest {
+            "D01" => Ok(Operation::Interpolate(coords, offset)),
+            "D02" => Ok(Operation::Move(coords)),
+            "D03" => Ok(Operation::Flash(coords)),
+            other => Err(GerberError::UnknownCommand(other.to_string())),
+        }
+    }
+}
+
+impl Parse for GCode {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        let trimmed = input.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("G04") {
+            return Ok(GCode::Comment(rest.trim().to_string()));
+        }
+        match trimmed.trim_end() {
+            "G01" => Ok(GCode::InterpolationMode(InterpolationMode::Linear)),
+            "G02" => Ok(GCode::InterpolationMode(InterpolationMode::ClockwiseCircular)),
+            "G03" => Ok(GCode::InterpolationMode(InterpolationMode::CounterclockwiseCircular)),
+            "G36" => Ok(GCode::RegionMode(true)),
+            "G37" => Ok(GCode::RegionMode(false)),
+            "G74" => Ok(GCode::QuadrantMode(QuadrantMode::Single)),
+            "G75" => Ok(GCode::QuadrantMode(QuadrantMode::Multi)),
+            other => Err(GerberError::UnknownCommand(other.to_string())),
+        }
+    }
+}
+
+impl Parse for MCode {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        match input.trim() {
+            "M02" => Ok(MCode::EndOfFile),
+            other => Err(GerberError::UnknownCommand(other.to_string())),
+        }
+    }
+}
+
+impl Parse for DCode {
+    type Context = ParseContext;
+
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        let trimmed = input.trim();
+        if let Some(rest) = trimmed.strip_prefix('D') {
+            let (num, tail) = take_number(rest);
+            if tail.is_empty() && !num.is_empty() {
+                let code: i32 = num
+                    .parse()
+                    .map_err(|_| GerberError::GenericError(format!("invalid aperture code in {:?}", input)))?;
+                // D00-D09 are reserved for operations (D01/D02/D03) and invalid codes; a bare
+                // `D03*` is a flash at the current point, never an aperture selection.
+                if code >= 10 {
+                    return Ok(DCode::SelectAperture(code));
+                }
+            }
+        }
+        Operation::parse(trimmed, ctx).map(DCode::Operation)
+    }
+}
+
+impl Parse for FunctionCode {
+    type Context = ParseContext;
+
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        let trimmed = input.trim_start();
+        match trimmed.chars().next() {
+            Some('G') => GCode::parse(trimmed, ctx).map(FunctionCode::GCode),
+            Some('M') => MCode::parse(trimmed, ctx).map(FunctionCode::MCode),
+            Some('D') | Some('X') | Some('Y') | Some('I') | Some('J') => {
+                DCode::parse(trimmed, ctx).map(FunctionCode::DCode)
+            },
+            _ => Err(GerberError::UnknownCommand(trimmed.to_string())),
+        }
+    }
+}
+
+impl Parse for Unit {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        match input {
+            "MM" => Ok(Unit::Millimeters),
+            "IN" => Ok(Unit::Inches),
+            other => Err(GerberError::UnknownCommand(format!("unit {:?}", other))),
+        }
+    }
+}
+
+impl Parse for Polarity {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        match input {
+            "D" => Ok(Polarity::Dark),
+            "C" => Ok(Polarity::Clear),
+            other => Err(GerberError::UnknownCommand(format!("polarity {:?}", other))),
+        }
+    }
+}
+
+impl Parse for StepAndRepeat {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        if input.is_empty() {
+            return Ok(StepAndRepeat::Close);
+        }
+        let rest = input.strip_prefix('X').ok_or_else(|| GerberError::UnknownCommand(input.to_string()))?;
+        let (rx, rest) = take_number(rest);
+        let rest = rest.strip_prefix('Y').ok_or_else(|| GerberError::UnknownCommand(input.to_string()))?;
+        let (ry, rest) = take_number(rest);
+        let rest = rest.strip_prefix('I').ok_or_else(|| GerberError::UnknownCommand(input.to_string()))?;
+        let (dx, rest) = take_decimal(rest);
+        let rest = rest.strip_prefix('J').ok_or_else(|| GerberError::UnknownCommand(input.to_string()))?;
+        let (dy, rest) = take_decimal(rest);
+        if !rest.is_empty() {
+            return Err(GerberError::UnknownCommand(input.to_string()));
+        }
+        let parse_err = || GerberError::GenericError(format!("invalid step-and-repeat block {:?}", input));
+        Ok(StepAndRepeat::Open {
+            repeat_x: rx.parse().map_err(|_| parse_err())?,
+            repeat_y: ry.parse().map_err(|_| parse_err())?,
+            distance_x: dx.parse().map_err(|_| parse_err())?,
+            distance_y: dy.parse().map_err(|_| parse_err())?,
+        })
+    }
+}
+
+impl Parse for MacroDecimal {
+    type Context = ParseContext;
+
+    /// Splits on the first arithmetic operator found, so a multi-operator expression parses
+    /// right-associatively (`a+b-c` becomes `a+(b-c)`) regardless of how the original
+    /// `MacroDecimal::Expression` tree was nested — parenthesization isn't modeled, and nothing
+    /// here is evaluated (there's no operator precedence either). Round-tripping through
+    /// `to_code` reproduces an equivalent source string, but not necessarily the original tree
+    /// shape.
+    #[allow(clippy::only_used_in_recursion)]
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        for (i, b) in input.bytes().enumerate().skip(1) {
+            let op = match b {
+                b'+' => Some(MacroOperator::Add),
+                b'-' => Some(MacroOperator::Subtract),
+                b'x' => Some(MacroOperator::Multiply),
+                b'/' => Some(MacroOperator::Divide),
+                _ => None,
+            };
+            if let Some(op) = op {
+                let left = MacroDecimal::parse(&input[..i], ctx)?;
+                let right = MacroDecimal::parse(&input[i + 1..], ctx)?;
+                return Ok(MacroDecimal::Expression(Box::new(left), op, Box::new(right)));
+            }
+        }
+        if let Some(rest) = input.strip_prefix('$') {
+            let number: u32 = rest
+                .parse()
+                .map_err(|_| GerberError::GenericError(format!("invalid macro variable {:?}", input)))?;
+            return Ok(MacroDecimal::Variable(number));
+        }
+        let value: f64 = input
+            .parse()
+            .map_err(|_| GerberError::GenericError(format!("invalid macro modifier {:?}", input)))?;
+        Ok(MacroDecimal::Value(value))
+    }
+}
+
+/// Parse a `x,y` modifier pair, as found in macro primitive fields.
+fn parse_point(x: &str, y: &str, ctx: &ParseContext) -> GerberResult<(MacroDecimal, MacroDecimal)> {
+    Ok((MacroDecimal::parse(x, ctx)?, MacroDecimal::parse(y, ctx)?))
+}
+
+impl Parse for MacroContent {
+    type Context = ParseContext;
+
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        let trimmed = input.trim();
+        if trimmed == "0" || trimmed.starts_with("0 ") {
+            return Ok(MacroContent::Comment(trimmed[1..].trim().to_string()));
+        }
+        let mut parts = trimmed.splitn(2, ',');
+        let code = parts.next().unwrap_or("");
+        let fields: Vec<&str> = match parts.next() {
+            Some(rest) => rest.split(',').collect(),
+            None => vec![],
+        };
+        let malformed = || GerberError::MalformedApertureDefinition(format!(
+            "aperture macro primitive {:?} has the wrong number of fields", trimmed
+        ));
+        match code {
+            "1" => {
+                if fields.len() != 5 {
+                    return Err(malformed());
+                }
+                Ok(MacroContent::Circle(CirclePrimitive {
+                    exposure: MacroDecimal::parse(fields[0], ctx)?,
+                    diameter: MacroDecimal::parse(fields[1], ctx)?,
+                    center: parse_point(fields[2], fields[3], ctx)?,
+                    rotation: MacroDecimal::parse(fields[4], ctx)?,
+                }))
+            },
+            "20" => {
+                if fields.len() != 7 {
+                    return Err(malformed());
+                }
+                Ok(MacroContent::VectorLine(VectorLinePrimitive {
+                    exposure: MacroDecimal::parse(fields[0], ctx)?,
+                    width: MacroDecimal::parse(fields[1], ctx)?,
+                    start: parse_point(fields[2], fields[3], ctx)?,
+                    end: parse_point(fields[4], fields[5], ctx)?,
+                    rotation: MacroDecimal::parse(fields[6], ctx)?,
+                }))
+            },
+            "21" => {
+                if fields.len() != 6 {
+                    return Err(malformed());
+                }
+                Ok(MacroContent::CenterLine(CenterLinePrimitive {
+                    exposure: MacroDecimal::parse(fields[0], ctx)?,
+                    width: MacroDecimal::parse(fields[1], ctx)?,
+                    height: MacroDecimal::parse(fields[2], ctx)?,
+                    center: parse_point(fields[3], fields[4], ctx)?,
+                    rotation: MacroDecimal::parse(fields[5], ctx)?,
+                }))
+            },
+            "4" => {
+                if fields.len() < 4 {
+                    return Err(malformed());
+                }
+                let exposure = MacroDecimal::parse(fields[0], ctx)?;
+                let n: usize = fields[1]
+                    .parse()
+                    .map_err(|_| GerberError::MalformedApertureDefinition(format!("invalid outline vertex count in {:?}", trimmed)))?;
+                if fields.len() != 2 + n * 2 + 1 {
+                    return Err(malformed());
+                }
+                let mut points = Vec::with_capacity(n);
+                for i in 0..n {
+                    points.push(parse_point(fields[2 + i * 2], fields[3 + i * 2], ctx)?);
+                }
+                let rotation = MacroDecimal::parse(fields[fields.len() - 1], ctx)?;
+                Ok(MacroContent::Outline(OutlinePrimitive { exposure, points, rotation }))
+            },
+            "5" => {
+                if fields.len() != 6 {
+                    return Err(malformed());
+                }
+                Ok(MacroContent::Polygon(PolygonPrimitive {
+                    exposure: MacroDecimal::parse(fields[0], ctx)?,
+                    vertices: MacroDecimal::parse(fields[1], ctx)?,
+                    center: parse_point(fields[2], fields[3], ctx)?,
+                    diameter: MacroDecimal::parse(fields[4], ctx)?,
+                    rotation: MacroDecimal::parse(fields[5], ctx)?,
+                }))
+            },
+            "7" => {
+                if fields.len() != 6 {
+                    return Err(malformed());
+                }
+                Ok(MacroContent::Thermal(ThermalPrimitive {
+                    center: parse_point(fields[0], fields[1], ctx)?,
+                    outer_diameter: MacroDecimal::parse(fields[2], ctx)?,
+                    inner_diameter: MacroDecimal::parse(fields[3], ctx)?,
+                    gap: MacroDecimal::parse(fields[4], ctx)?,
+                    rotation: MacroDecimal::parse(fields[5], ctx)?,
+                }))
+            },
+            other => Err(GerberError::UnknownCommand(format!("aperture macro primitive code {}", other))),
+        }
+    }
+}
+
+impl Parse for ApertureMacro {
+    type Context = ParseContext;
+
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        let mut segments = input.split('*').filter(|s| !s.trim().is_empty());
+        let name = segments
+            .next()
+            .ok_or(GerberError::UnexpectedEof)?
+            .trim()
+            .to_string();
+        let mut content = Vec::new();
+        for segment in segments {
+            content.push(MacroContent::parse(segment, ctx)?);
+        }
+        Ok(ApertureMacro { name, content })
+    }
+}
+
+impl Parse for Aperture {
+    type Context = ParseContext;
+
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        let mut parts = input.splitn(2, ',');
+        let shape = parts.next().unwrap_or("");
+        let fields: Vec<&str> = match parts.next() {
+            Some(rest) => rest.split('X').collect(),
+            None => vec![],
+        };
+        let decimal = |s: &str| -> GerberResult<f64> {
+            s.parse().map_err(|_| GerberError::MalformedApertureDefinition(format!("invalid number {:?}", s)))
+        };
+        match shape {
+            "C" => {
+                if fields.is_empty() || fields.len() > 2 {
+                    return Err(GerberError::MalformedApertureDefinition(format!("{:?}", input)));
+                }
+                Ok(Aperture::Circle(Circle {
+                    diameter: decimal(fields[0])?,
+                    hole_diameter: fields.get(1).map(|f| decimal(f)).transpose()?,
+                }))
+            },
+            "R" | "O" => {
+                if fields.len() < 2 || fields.len() > 3 {
+                    return Err(GerberError::MalformedApertureDefinition(format!("{:?}", input)));
+                }
+                let rect = Rectangular {
+                    x: decimal(fields[0])?,
+                    y: decimal(fields[1])?,
+                    hole_diameter: fields.get(2).map(|f| decimal(f)).transpose()?,
+                };
+                Ok(if shape == "R" { Aperture::Rectangle(rect) } else { Aperture::Obround(rect) })
+            },
+            "P" => {
+                if fields.len() < 2 || fields.len() > 4 {
+                    return Err(GerberError::MalformedApertureDefinition(format!("{:?}", input)));
+                }
+                Ok(Aperture::Polygon(Polygon {
+                    diameter: decimal(fields[0])?,
+                    vertices: fields[1]
+                        .parse()
+                        .map_err(|_| GerberError::MalformedApertureDefinition(format!("{:?}", input)))?,
+                    rotation: fields.get(2).map(|f| decimal(f)).transpose()?,
+                    hole_diameter: fields.get(3).map(|f| decimal(f)).transpose()?,
+                }))
+            },
+            "" => Err(GerberError::MalformedApertureDefinition(input.to_string())),
+            name => {
+                let args: GerberResult<Vec<MacroDecimal>> =
+                    fields.into_iter().map(|f| MacroDecimal::parse(f, ctx)).collect();
+                Ok(Aperture::Macro { name: name.to_string(), args: args? })
+            },
+        }
+    }
+}
+
+impl Parse for ApertureDefinition {
+    type Context = ParseContext;
+
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        let (code, rest) = take_number(input);
+        if code.is_empty() {
+            return Err(GerberError::MalformedApertureDefinition(input.to_string()));
+        }
+        Ok(ApertureDefinition {
+            code: code
+                .parse()
+                .map_err(|_| GerberError::MalformedApertureDefinition(input.to_string()))?,
+            aperture: Aperture::parse(rest, ctx)?,
+        })
+    }
+}
+
+impl Parse for Part {
+    type Context = ParseContext;
+
+    fn parse(input: &str, _ctx: &ParseContext) -> GerberResult<Self> {
+        if let Some(description) = input.strip_prefix("Other,") {
+            return Ok(Part::Other(description.to_string()));
+        }
+        Err(GerberError::UnknownCommand(format!(".Part value {:?}", input)))
+    }
+}
+
+impl Parse for FileAttribute {
+    type Context = ParseContext;
+
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        if let Some(rest) = input.strip_prefix(".Part,") {
+            return Ok(FileAttribute::Part(Part::parse(rest, ctx)?));
+        }
+        Err(GerberError::UnknownCommand(format!("file attribute {:?}", input)))
+    }
+}
+
+impl Parse for ExtendedCode {
+    type Context = ParseContext;
+
+    fn parse(input: &str, ctx: &ParseContext) -> GerberResult<Self> {
+        if let Some(rest) = input.strip_prefix("FSLA") {
+            let rest = rest.strip_prefix('X').ok_or_else(|| GerberError::UnknownCommand(input.to_string()))?;
+            if rest.len() < 4 {
+                return Err(GerberError::UnknownCommand(input.to_string()));
+            }
+            let integer: u8 = rest[0..1]
+                .parse()
+                .map_err(|_| GerberError::GenericError(format!("invalid coordinate format {:?}", input)))?;
+            let decimal: u8 = rest[1..2]
+                .parse()
+                .map_err(|_| GerberError::GenericError(format!("invalid coordinate format {:?}", input)))?;
+            return Ok(ExtendedCode::CoordinateFormat(integer, decimal));
+        }
+        if let Some(rest) = input.strip_prefix("MO") {
+            return Ok(ExtendedCode::Unit(Unit::parse(rest, ctx)?));
+        }
+        if let Some(rest) = input.strip_prefix("AD") {
+            return Ok(ExtendedCode::ApertureDefinition(ApertureDefinition::parse(rest, ctx)?));
+        }
+        if let Some(rest) = input.strip_prefix("LP") {
+            return Ok(ExtendedCode::LoadPolarity(Polarity::parse(rest, ctx)?));
+        }
+        if let Some(rest) = input.strip_prefix("SR") {
+            return Ok(ExtendedCode::StepAndRepeat(StepAndRepeat::parse(rest, ctx)?));
+        }
+        if let Some(rest) = input.strip_prefix("TD") {
+            return Ok(ExtendedCode::DeleteAttribute(rest.to_string()));
+        }
+        if let Some(rest) = input.strip_prefix("TF") {
+            return Ok(ExtendedCode::FileAttribute(FileAttribute::parse(rest, ctx)?));
+        }
+        if let Some(rest) = input.strip_prefix("AM") {
+            return Ok(ExtendedCode::ApertureMacro(ApertureMacro::parse(rest, ctx)?));
+        }
+        Err(GerberError::UnknownCommand(input.to_string()))
+    }
+}
+
+/// Parse a full Gerber (RS-274X) source file into its command tree.
+///
+/// The input is split on `*`-terminated function codes and `%...%`-delimited extended codes;
+/// each is dispatched to [`Parse`](trait.Parse.html). Serializing the returned commands with
+/// [`GerberCode`](trait.GerberCode.html) reproduces equivalent Gerber code.
+pub fn parse_gerber<R: Read>(mut reader: R) -> GerberResult<Vec<Command>> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+
+    let mut commands = Vec::new();
+    let ctx = ParseContext;
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c == '%' {
+            chars.next();
+            let mut block = String::new();
+            loop {
+                match chars.next() {
+                    Some('%') => break,
+                    Some(ch) => block.push(ch),
+                    None => return Err(GerberError::UnexpectedEof),
+                }
+            }
+            let block = block.strip_suffix('*').unwrap_or(&block);
+            let code = ExtendedCode::parse(block, &ctx)?;
+            commands.push(Command::ExtendedCode(code));
+        } else if c.is_whitespace() {
+            chars.next();
+        } else {
+            let mut token = String::new();
+            loop {
+                match chars.next() {
+                    Some('*') => break,
+                    Some(ch) => token.push(ch),
+                    None => return Err(GerberError::UnexpectedEof),
+                }
+            }
+            if token.trim().is_empty() {
+                continue;
+            }
+            commands.push(Command::FunctionCode(FunctionCode::parse(&token, &ctx)?));
+        }
+    }
+
+    Ok(commands)
+}
@@ -0,0 +1,173 @@
+//! The Gerber command and code types.
+
+use aperture_macros::{ApertureMacro, MacroDecimal};
+use attributes::FileAttribute;
+use coordinates::{CoordinateOffset, Coordinates};
+
+/// A full Gerber command: either a function code or an extended code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    FunctionCode(FunctionCode),
+    ExtendedCode(ExtendedCode),
+}
+
+/// A function code, the `*`-terminated commands that make up the bulk of a Gerber file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FunctionCode {
+    GCode(GCode),
+    MCode(MCode),
+    DCode(DCode),
+}
+
+/// Preparatory ("G") codes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GCode {
+    /// A human readable comment (`G04`).
+    Comment(String),
+    /// Sets the interpolation mode (`G01`/`G02`/`G03`).
+    InterpolationMode(InterpolationMode),
+    /// Opens (`G36`) or closes (`G37`) a region statement.
+    RegionMode(bool),
+    /// Sets the quadrant mode for circular interpolation (`G74`/`G75`).
+    QuadrantMode(QuadrantMode),
+}
+
+/// The interpolation mode used by draw/arc operations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterpolationMode {
+    /// Linear interpolation (`G01`).
+    Linear,
+    /// Clockwise circular interpolation (`G02`).
+    ClockwiseCircular,
+    /// Counterclockwise circular interpolation (`G03`).
+    CounterclockwiseCircular,
+}
+
+/// The quadrant mode used by circular interpolation (`G74`/`G75`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuadrantMode {
+    /// Single-quadrant mode (`G74`).
+    Single,
+    /// Multi-quadrant mode (`G75`).
+    Multi,
+}
+
+/// Miscellaneous ("M") codes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MCode {
+    /// Marks the end of the Gerber file (`M02`).
+    EndOfFile,
+}
+
+/// Draw ("D") codes: aperture selection and operations.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DCode {
+    /// Select the current aperture by its code (`D10` and up).
+    SelectAperture(i32),
+    /// Perform a draw/move/flash operation (`D01`/`D02`/`D03`).
+    Operation(Operation),
+}
+
+/// A draw, move or flash operation, performed at the currently selected aperture.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// Draw from the current point to the given coordinates (`D01`).
+    Interpolate(Coordinates, Option<CoordinateOffset>),
+    /// Move to the given coordinates without drawing (`D02`).
+    Move(Coordinates),
+    /// Flash the current aperture at the given coordinates (`D03`).
+    Flash(Coordinates),
+}
+
+/// Extended codes: the `%...*%` commands that configure the graphics state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtendedCode {
+    /// Set the coordinate format (`%FS%`), as `(integer_digits, decimal_digits)`.
+    CoordinateFormat(u8, u8),
+    /// Set the unit used for coordinates and apertures (`%MO%`).
+    Unit(Unit),
+    /// Define a new aperture (`%AD%`).
+    ApertureDefinition(ApertureDefinition),
+    /// Set the polarity used by subsequent operations (`%LP%`).
+    LoadPolarity(Polarity),
+    /// Open or close a step-and-repeat block (`%SR%`).
+    StepAndRepeat(StepAndRepeat),
+    /// Delete a previously set attribute, or all attributes (`%TD%`).
+    DeleteAttribute(String),
+    /// Attach a file attribute (`%TF%`).
+    FileAttribute(FileAttribute),
+    /// Define a new aperture macro (`%AM%`).
+    ApertureMacro(ApertureMacro),
+}
+
+/// The unit used to interpret coordinates and aperture dimensions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Millimeters,
+    Inches,
+}
+
+/// The polarity of subsequent graphics objects.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Polarity {
+    Dark,
+    Clear,
+}
+
+/// A step-and-repeat block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepAndRepeat {
+    /// Open a step-and-repeat block, repeating the enclosed commands on a grid.
+    Open {
+        repeat_x: u32,
+        repeat_y: u32,
+        distance_x: f64,
+        distance_y: f64,
+    },
+    /// Close the currently open step-and-repeat block.
+    Close,
+}
+
+/// An aperture definition: an aperture code paired with its shape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApertureDefinition {
+    pub code: i32,
+    pub aperture: Aperture,
+}
+
+/// The shape of an aperture.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aperture {
+    Circle(Circle),
+    Rectangle(Rectangular),
+    Obround(Rectangular),
+    Polygon(Polygon),
+    /// An aperture instantiated from a user-defined aperture macro, with its modifiers.
+    Macro { name: String, args: Vec<MacroDecimal> },
+    /// An aperture template not otherwise modelled, passed through verbatim.
+    Other(String),
+}
+
+/// A circular aperture, optionally with a center hole.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    pub diameter: f64,
+    pub hole_diameter: Option<f64>,
+}
+
+/// A rectangular or obround aperture, optionally with a center hole.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rectangular {
+    pub x: f64,
+    pub y: f64,
+    pub hole_diameter: Option<f64>,
+}
+
+/// A regular polygon aperture, optionally rotated and/or with a center hole.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Polygon {
+    pub diameter: f64,
+    pub vertices: u8,
+    pub rotation: Option<f64>,
+    pub hole_diameter: Option<f64>,
+}